@@ -71,7 +71,7 @@ fn validate_path_within_project(path: &str, project_path: &str) -> Result<(), St
     Ok(())
 }
 
-const IGNORED_DIRS: &[&str] = &[
+pub const IGNORED_DIRS: &[&str] = &[
     "node_modules",
     ".git",
     ".next",
@@ -313,7 +313,7 @@ fn build_file_tree(dir: &Path, base_path: &str) -> Vec<FileNode> {
     nodes
 }
 
-fn should_ignore_path(path: &Path) -> bool {
+pub(crate) fn should_ignore_path(path: &Path) -> bool {
     for component in path.components() {
         let name = component.as_os_str().to_string_lossy();
         if IGNORED_DIRS.contains(&name.as_ref()) {
@@ -1,11 +1,21 @@
+mod jobs;
+mod log_parser;
+mod watch;
+
+use super::fs::IGNORED_DIRS;
 use super::util::command;
+pub use jobs::CompileJobInfo;
+use jobs::{kill_process_tree, CompileJobHandle, CompileJobs};
+pub use log_parser::LaTeXDiagnostic;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::process::Stdio;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
 use tauri::{AppHandle, Emitter, State};
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Child;
-use tokio::sync::Mutex;
+use tokio::time::Instant;
+use watch::CompileWatches;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LaTeXDistribution {
@@ -46,33 +56,104 @@ pub struct LaTeXCompilersStatus {
     pub latexmk: CompilerInfo,
 }
 
+/// Optional build settings for `latex_compile`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompileOptions {
+    /// Compile job id; defaults to the path of the main file
+    pub job_id: Option<String>,
+    /// Directory for the PDF and build products, relative to the project directory
+    pub output_directory: Option<String>,
+    /// Separate directory for auxiliary files (latexmk and MiKTeX engines)
+    pub aux_directory: Option<String>,
+    /// Wall-clock limit for the whole build in seconds; 0 disables it
+    pub timeout_secs: Option<u64>,
+    /// Kill a tool that writes no output for this many seconds; 0 disables it
+    pub idle_timeout_secs: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompilationResult {
+    pub job_id: String,
     pub success: bool,
+    /// "succeeded", "failed" or "timed_out"
+    pub outcome: String,
     pub exit_code: Option<i32>,
     pub pdf_path: Option<String>,
+    /// SyncTeX file written by the build, for PDF/source navigation
+    pub synctex_path: Option<String>,
     pub error: Option<String>,
+    /// Diagnostics parsed from the engine's .log file
+    pub diagnostics: Vec<LaTeXDiagnostic>,
+    /// Last lines of the log when the build timed out
+    pub log_tail: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompileOutputEvent {
+    pub job_id: String,
     pub line: String,
     pub is_error: bool,
     pub is_warning: bool,
 }
 
-pub struct LaTeXCompilationState {
-    pub current_process: Arc<Mutex<Option<Child>>>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileProgressEvent {
+    pub job_id: String,
+    pub pass: u32,
+    pub max_passes: u32,
+    /// Tool being run: the engine, "bibtex", "biber" or "makeindex"
+    pub tool: String,
+    pub message: String,
 }
 
-impl Default for LaTeXCompilationState {
-    fn default() -> Self {
-        Self {
-            current_process: Arc::new(Mutex::new(None)),
-        }
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileDiagnosticsEvent {
+    pub job_id: String,
+    pub diagnostics: Vec<LaTeXDiagnostic>,
+}
+
+#[derive(Default)]
+pub struct LaTeXCompilationState {
+    pub jobs: CompileJobs,
+    pub watches: CompileWatches,
 }
 
+/// Maximum number of engine runs when we orchestrate the build ourselves
+const MAX_COMPILE_PASSES: u32 = 5;
+
+/// Default wall-clock limit for a build
+const DEFAULT_COMPILE_TIMEOUT_SECS: u64 = 600;
+
+/// Default time a tool may go without writing to stdout, stderr or its log
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 180;
+
+const WATCHDOG_POLL_MS: u64 = 500;
+
+/// Lines of log kept for a timed-out build
+const LOG_TAIL_LINES: usize = 20;
+
+/// Command-line switches only latexmk understands, dropped when falling back to an engine
+const LATEXMK_ONLY_ARGS: &[&str] = &[
+    "-pdf",
+    "-pdfxe",
+    "-pdflua",
+    "-pdflatex",
+    "-xelatex",
+    "-lualatex",
+    "-dvi",
+    "-ps",
+    "-f",
+    "-g",
+    "-gg",
+    "-bibtex",
+    "-nobibtex",
+    "-quiet",
+    "-silent",
+    "-cd",
+    "-norc",
+];
+
 async fn find_compiler(name: &str) -> CompilerInfo {
     let which_cmd = if cfg!(target_os = "windows") {
         "where"
@@ -261,6 +342,7 @@ async fn is_compiler_detectable_after_install() -> bool {
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn latex_compile(
     app: AppHandle,
     state: State<'_, LaTeXCompilationState>,
@@ -269,14 +351,207 @@ pub async fn latex_compile(
     main_file: String,
     args: Vec<String>,
     custom_path: Option<String>,
+    options: Option<CompileOptions>,
 ) -> Result<CompilationResult, String> {
-    // Stop any existing compilation
-    {
-        let mut process_guard = state.current_process.lock().await;
-        if let Some(mut child) = process_guard.take() {
-            let _ = child.kill().await;
+    let request = CompileRequest {
+        directory,
+        compiler,
+        main_file,
+        args,
+        custom_path,
+        options: options.unwrap_or_default(),
+    };
+    run_compile_job(&app, &state.jobs, request).await
+}
+
+/// Everything needed to run one build of a document
+#[derive(Debug, Clone)]
+struct CompileRequest {
+    directory: String,
+    compiler: String,
+    main_file: String,
+    args: Vec<String>,
+    custom_path: Option<String>,
+    options: CompileOptions,
+}
+
+impl CompileRequest {
+    /// Jobs are keyed by document by default, so recompiling one replaces its running build
+    fn job_id(&self) -> String {
+        self.options.job_id.clone().unwrap_or_else(|| {
+            std::path::Path::new(&self.directory)
+                .join(&self.main_file)
+                .to_string_lossy()
+                .to_string()
+        })
+    }
+}
+
+/// Queue a build as a compile job, run it, and remove the job when done.
+async fn run_compile_job(
+    app: &AppHandle,
+    jobs: &CompileJobs,
+    request: CompileRequest,
+) -> Result<CompilationResult, String> {
+    let job = jobs
+        .submit(CompileJobInfo {
+            job_id: request.job_id(),
+            directory: request.directory.clone(),
+            main_file: request.main_file.clone(),
+            compiler: request.compiler.clone(),
+            status: String::new(),
+            submitted_at: 0,
+        })
+        .await;
+
+    let result = match jobs.wait_for_slot(&job).await {
+        Ok(()) => compile_document(app, &job, request).await,
+        Err(e) => Err(e),
+    };
+
+    jobs.finish(&job).await;
+    result
+}
+
+/// Where a build writes its products
+#[derive(Debug, Clone)]
+struct BuildDirs {
+    /// Directory for the PDF and SyncTeX file
+    output: std::path::PathBuf,
+    /// Directory for .aux, .log and the other intermediate files
+    aux: std::path::PathBuf,
+}
+
+impl BuildDirs {
+    /// Resolve output and aux directories (relative to the project directory).
+    fn resolve(directory: &str, output: Option<&str>, aux: Option<&str>) -> Self {
+        let project = std::path::Path::new(directory);
+        let output = match output.map(str::trim).filter(|o| !o.is_empty()) {
+            Some(o) => project.join(o),
+            None => project.to_path_buf(),
+        };
+        let aux = match aux.map(str::trim).filter(|a| !a.is_empty()) {
+            Some(a) => project.join(a),
+            None => output.clone(),
+        };
+        Self { output, aux }
+    }
+
+    fn pdf(&self, job_name: &str) -> std::path::PathBuf {
+        self.output.join(format!("{}.pdf", job_name))
+    }
+
+    fn aux_file(&self, job_name: &str, ext: &str) -> std::path::PathBuf {
+        self.aux.join(format!("{}.{}", job_name, ext))
+    }
+
+    /// The SyncTeX file, which lands next to the PDF or in the aux directory
+    fn synctex(&self, job_name: &str) -> Option<std::path::PathBuf> {
+        [&self.output, &self.aux]
+            .iter()
+            .flat_map(|dir| {
+                ["synctex.gz", "synctex"]
+                    .iter()
+                    .map(move |ext| dir.join(format!("{}.{}", job_name, ext)))
+            })
+            .find(|path| path.exists())
+    }
+
+    /// The log file; engines that ignore the aux directory write it next to the PDF
+    fn log(&self, job_name: &str) -> std::path::PathBuf {
+        let log = self.aux_file(job_name, "log");
+        if log.exists() {
+            log
+        } else {
+            self.output.join(format!("{}.log", job_name))
         }
     }
+}
+
+/// Shared state for the steps of one build
+struct BuildContext<'a> {
+    app: &'a AppHandle,
+    job: &'a CompileJobHandle,
+    /// Project directory the engine runs in
+    directory: &'a str,
+    dirs: BuildDirs,
+    /// Output file stem (the engine's \jobname)
+    job_name: String,
+    started_at: Instant,
+    timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    /// Why the build was killed by the watchdog, if it was
+    timed_out: StdMutex<Option<String>>,
+    /// Most recent tool output, used when the log is missing
+    recent_output: Arc<StdMutex<VecDeque<String>>>,
+}
+
+impl BuildContext<'_> {
+    fn timeout_reason(&self) -> Option<String> {
+        self.timed_out.lock().ok().and_then(|reason| reason.clone())
+    }
+}
+
+/// A limit in seconds from the compile options, where 0 means no limit
+fn limit_from_secs(secs: Option<u64>, default: u64) -> Option<Duration> {
+    match secs.unwrap_or(default) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+async fn compile_document(
+    app: &AppHandle,
+    job: &CompileJobHandle,
+    request: CompileRequest,
+) -> Result<CompilationResult, String> {
+    let CompileRequest {
+        directory,
+        mut compiler,
+        main_file,
+        mut args,
+        custom_path,
+        options,
+    } = request;
+
+    // Engines name their output after the file stem
+    let job_name = std::path::Path::new(&main_file)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| main_file.clone());
+
+    let mut ctx = BuildContext {
+        app,
+        job,
+        directory: &directory,
+        dirs: BuildDirs::resolve(
+            &directory,
+            options.output_directory.as_deref(),
+            options.aux_directory.as_deref(),
+        ),
+        job_name,
+        started_at: Instant::now(),
+        timeout: limit_from_secs(options.timeout_secs, DEFAULT_COMPILE_TIMEOUT_SECS),
+        idle_timeout: limit_from_secs(options.idle_timeout_secs, DEFAULT_IDLE_TIMEOUT_SECS),
+        timed_out: StdMutex::new(None),
+        recent_output: Arc::new(StdMutex::new(VecDeque::new())),
+    };
+
+    // Without latexmk we orchestrate the passes ourselves
+    if compiler == "latexmk" && custom_path.is_none() && !find_compiler("latexmk").await.available {
+        let engine = latexmk_fallback_engine(&args);
+        emit_compile_progress(
+            &ctx,
+            0,
+            engine,
+            format!(
+                "latexmk was not found, building with {} and automatic passes",
+                engine
+            ),
+        );
+        args.retain(|arg| !LATEXMK_ONLY_ARGS.contains(&arg.as_str()));
+        compiler = engine.to_string();
+    }
 
     // Determine the compiler executable
     let compiler_path = if let Some(ref path) = custom_path {
@@ -292,20 +567,169 @@ pub async fn latex_compile(
         "-synctex=1".to_string(),
     ];
 
+    // Route build products into the output and aux directories
+    if options.output_directory.is_some() || options.aux_directory.is_some() {
+        cmd_args.extend(output_directory_args(&compiler, &compiler_path, &mut ctx.dirs).await);
+        prepare_build_dirs(&directory, &ctx.dirs)?;
+    }
+
     // Add user-provided arguments
     cmd_args.extend(args);
 
     // Add the main file
     cmd_args.push(main_file.clone());
 
-    // Create the command
-    let mut cmd = command(&compiler_path);
-    cmd.current_dir(&directory)
-        .args(&cmd_args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
+    let exit_code = if compiler == "latexmk" {
+        run_compile_step(&ctx, &compiler_path, &cmd_args, &directory, &[]).await?
+    } else {
+        run_build_passes(&ctx, &compiler, &compiler_path, &cmd_args).await?
+    };
 
-    // Ensure PATH includes common TeX directories
+    // Determine the PDF path
+    let pdf_path = ctx.dirs.pdf(&ctx.job_name);
+    let pdf_exists = pdf_path.exists();
+
+    // Parse the .log file for structured diagnostics
+    let log_path = ctx.dirs.log(&ctx.job_name);
+    let diagnostics = read_log_diagnostics(&log_path.to_string_lossy());
+    let _ = app.emit(
+        "latex-compile-diagnostics",
+        CompileDiagnosticsEvent {
+            job_id: job.job_id.clone(),
+            diagnostics: diagnostics.clone(),
+        },
+    );
+
+    let timeout_reason = ctx.timeout_reason();
+    let success = timeout_reason.is_none() && exit_code == Some(0) && pdf_exists;
+    let log_tail = if timeout_reason.is_some() {
+        read_log_tail(&log_path, &ctx.recent_output)
+    } else {
+        Vec::new()
+    };
+
+    Ok(CompilationResult {
+        job_id: job.job_id.clone(),
+        success,
+        outcome: if timeout_reason.is_some() {
+            "timed_out"
+        } else if success {
+            "succeeded"
+        } else {
+            "failed"
+        }
+        .to_string(),
+        exit_code,
+        pdf_path: if pdf_exists {
+            Some(pdf_path.to_string_lossy().to_string())
+        } else {
+            None
+        },
+        synctex_path: ctx
+            .dirs
+            .synctex(&ctx.job_name)
+            .map(|p| p.to_string_lossy().to_string()),
+        error: if let Some(reason) = timeout_reason {
+            Some(reason)
+        } else if !success && !pdf_exists {
+            Some("PDF was not generated".to_string())
+        } else if !success {
+            Some(format!("Compilation failed with exit code {:?}", exit_code))
+        } else {
+            None
+        },
+        diagnostics,
+        log_tail,
+    })
+}
+
+/// The last lines of the log, or of the tool output if the log was never written
+fn read_log_tail(
+    log_path: &std::path::Path,
+    recent_output: &StdMutex<VecDeque<String>>,
+) -> Vec<String> {
+    let log = std::fs::read(log_path)
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .unwrap_or_default();
+    let lines: Vec<String> = log.lines().map(str::to_string).collect();
+    if !lines.is_empty() {
+        return lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].to_vec();
+    }
+    recent_output
+        .lock()
+        .map(|output| output.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Command-line switches that send build products to the configured directories.
+/// Engines other than MiKTeX's cannot separate aux files from the PDF, in which
+/// case `dirs` is updated to keep aux files in the output directory.
+async fn output_directory_args(
+    compiler: &str,
+    compiler_path: &str,
+    dirs: &mut BuildDirs,
+) -> Vec<String> {
+    let output = dirs.output.to_string_lossy().to_string();
+    let aux = dirs.aux.to_string_lossy().to_string();
+
+    if compiler == "latexmk" {
+        let mut args = vec![format!("-outdir={}", output)];
+        if dirs.aux != dirs.output {
+            args.push(format!("-auxdir={}", aux));
+        }
+        return args;
+    }
+
+    let mut args = vec![format!("-output-directory={}", output)];
+    if dirs.aux != dirs.output {
+        let is_miktex = get_compiler_version(compiler, compiler_path)
+            .await
+            .is_some_and(|v| v.contains("MiKTeX"));
+        if is_miktex {
+            args.push(format!("-aux-directory={}", aux));
+        } else {
+            dirs.aux = dirs.output.clone();
+        }
+    }
+    args
+}
+
+/// Create the output and aux directories. Engines also need the project's
+/// subdirectories mirrored there, or writing the .aux of an `\include`d
+/// `chapters/intro.tex` fails.
+fn prepare_build_dirs(directory: &str, dirs: &BuildDirs) -> Result<(), String> {
+    use walkdir::WalkDir;
+
+    let project = std::path::Path::new(directory);
+    for build_dir in [&dirs.output, &dirs.aux] {
+        std::fs::create_dir_all(build_dir)
+            .map_err(|e| format!("Failed to create build directory: {}", e))?;
+
+        for entry in WalkDir::new(project)
+            .min_depth(1)
+            .max_depth(4)
+            .into_iter()
+            .filter_entry(|e| {
+                let name = e.file_name().to_string_lossy();
+                !name.starts_with('.')
+                    && !IGNORED_DIRS.contains(&name.as_ref())
+                    && e.path() != dirs.output.as_path()
+                    && e.path() != dirs.aux.as_path()
+            })
+            .filter_map(|e| e.ok())
+        {
+            if entry.file_type().is_dir() {
+                if let Ok(relative) = entry.path().strip_prefix(project) {
+                    let _ = std::fs::create_dir_all(build_dir.join(relative));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// PATH for TeX subprocesses, including common TeX directories
+fn tex_env_path() -> String {
     let env_path = std::env::var("PATH").unwrap_or_default();
     #[cfg(target_os = "macos")]
     let env_path = {
@@ -318,16 +742,47 @@ pub async fn latex_compile(
             env_path
         }
     };
-    cmd.env("PATH", env_path);
+    env_path
+}
+
+/// Run one build tool (engine, bibtex, biber, makeindex), streaming its output as
+/// `latex-compile-output` events. Returns the exit code.
+async fn run_compile_step(
+    ctx: &BuildContext<'_>,
+    program: &str,
+    args: &[String],
+    cwd: &str,
+    envs: &[(&str, String)],
+) -> Result<Option<i32>, String> {
+    let job = ctx.job;
+
+    // Create the command
+    let mut cmd = command(program);
+    cmd.current_dir(cwd)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd.env("PATH", tex_env_path());
+    for (key, value) in envs {
+        cmd.env(key, value);
+    }
+    // Own process group, so a timeout can kill everything the tool started
+    #[cfg(unix)]
+    cmd.process_group(0);
 
     // Spawn the process
     let child = cmd
         .spawn()
-        .map_err(|e| format!("Failed to start compiler: {}", e))?;
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
 
-    // Store the child process
+    // Store the child process so the job can be cancelled
     {
-        let mut process_guard = state.current_process.lock().await;
+        let mut process_guard = job.process.lock().await;
+        if job.is_cancelled() {
+            let mut child = child;
+            let _ = child.kill().await;
+            return Err("Compilation was cancelled".to_string());
+        }
         *process_guard = Some(child);
     }
 
@@ -335,7 +790,7 @@ pub async fn latex_compile(
     let stdout;
     let stderr;
     {
-        let mut process_guard = state.current_process.lock().await;
+        let mut process_guard = job.process.lock().await;
         if let Some(ref mut child) = *process_guard {
             stdout = child.stdout.take();
             stderr = child.stderr.take();
@@ -344,24 +799,22 @@ pub async fn latex_compile(
         }
     }
 
-    // Stream output
-    let app_clone = app.clone();
+    // Stream output, noting when the tool last showed signs of life
+    let last_activity = Arc::new(StdMutex::new(Instant::now()));
     let stdout_handle = if let Some(stdout) = stdout {
-        let app = app_clone.clone();
+        let app = ctx.app.clone();
+        let job_id = job.job_id.clone();
+        let last_activity = last_activity.clone();
+        let recent_output = ctx.recent_output.clone();
         Some(tokio::spawn(async move {
             let reader = BufReader::new(stdout);
             let mut lines = reader.lines();
             while let Ok(Some(line)) = lines.next_line().await {
-                let is_error = line.contains("!")
-                    || line.contains("Error")
-                    || line.contains("error")
-                    || line.starts_with("l.");
-                let is_warning = line.contains("Warning")
-                    || line.contains("warning")
-                    || line.contains("Overfull")
-                    || line.contains("Underfull");
+                record_activity(&last_activity, &recent_output, &line);
+                let (is_error, is_warning) = log_parser::classify_line(&line);
 
                 let event = CompileOutputEvent {
+                    job_id: job_id.clone(),
                     line,
                     is_error,
                     is_warning,
@@ -374,12 +827,17 @@ pub async fn latex_compile(
     };
 
     let stderr_handle = if let Some(stderr) = stderr {
-        let app = app_clone;
+        let app = ctx.app.clone();
+        let job_id = job.job_id.clone();
+        let last_activity = last_activity.clone();
+        let recent_output = ctx.recent_output.clone();
         Some(tokio::spawn(async move {
             let reader = BufReader::new(stderr);
             let mut lines = reader.lines();
             while let Ok(Some(line)) = lines.next_line().await {
+                record_activity(&last_activity, &recent_output, &line);
                 let event = CompileOutputEvent {
+                    job_id: job_id.clone(),
                     line,
                     is_error: true,
                     is_warning: false,
@@ -391,69 +849,474 @@ pub async fn latex_compile(
         None
     };
 
-    // Wait for output streams to finish
-    if let Some(handle) = stdout_handle {
-        let _ = handle.await;
-    }
-    if let Some(handle) = stderr_handle {
-        let _ = handle.await;
+    // Wait for output streams to finish, unless the watchdog fires first
+    let streams = async {
+        if let Some(handle) = stdout_handle {
+            let _ = handle.await;
+        }
+        if let Some(handle) = stderr_handle {
+            let _ = handle.await;
+        }
+    };
+    let timeout_reason = tokio::select! {
+        _ = streams => None,
+        reason = watchdog(ctx, program, &last_activity) => Some(reason),
+    };
+    if let Some(reason) = timeout_reason {
+        if let Some(mut child) = job.process.lock().await.take() {
+            kill_process_tree(&mut child).await;
+        }
+        let _ = ctx.app.emit(
+            "latex-compile-output",
+            CompileOutputEvent {
+                job_id: job.job_id.clone(),
+                line: reason.clone(),
+                is_error: true,
+                is_warning: false,
+            },
+        );
+        if let Ok(mut timed_out) = ctx.timed_out.lock() {
+            *timed_out = Some(reason);
+        }
+        return Ok(None);
     }
 
     // Wait for the process to complete
-    let exit_code;
-    {
-        let mut process_guard = state.current_process.lock().await;
-        if let Some(mut child) = process_guard.take() {
-            match child.wait().await {
-                Ok(status) => {
-                    exit_code = status.code();
+    let mut process_guard = job.process.lock().await;
+    match process_guard.take() {
+        Some(mut child) if !job.is_cancelled() => match child.wait().await {
+            Ok(status) => Ok(status.code()),
+            Err(e) => Err(format!("Failed to wait for {}: {}", program, e)),
+        },
+        _ => Err("Compilation was cancelled".to_string()),
+    }
+}
+
+fn record_activity(
+    last_activity: &StdMutex<Instant>,
+    recent_output: &StdMutex<VecDeque<String>>,
+    line: &str,
+) {
+    if let Ok(mut last) = last_activity.lock() {
+        *last = Instant::now();
+    }
+    if let Ok(mut output) = recent_output.lock() {
+        if output.len() >= LOG_TAIL_LINES {
+            output.pop_front();
+        }
+        output.push_back(line.to_string());
+    }
+}
+
+/// Resolve with a reason once the build passes its deadline or the tool goes quiet.
+/// Growth of the log file counts as activity, since TeX writes there more than to stdout.
+async fn watchdog(
+    ctx: &BuildContext<'_>,
+    program: &str,
+    last_activity: &StdMutex<Instant>,
+) -> String {
+    if ctx.timeout.is_none() && ctx.idle_timeout.is_none() {
+        return std::future::pending().await;
+    }
+
+    let log_path = ctx.dirs.log(&ctx.job_name);
+    let log_len = |path: &std::path::Path| std::fs::metadata(path).map(|m| m.len()).ok();
+    let mut last_log_len = log_len(&log_path);
+    loop {
+        tokio::time::sleep(Duration::from_millis(WATCHDOG_POLL_MS)).await;
+        let now = Instant::now();
+
+        if let Some(timeout) = ctx.timeout {
+            if now.duration_since(ctx.started_at) >= timeout {
+                return format!("Compilation timed out after {} seconds", timeout.as_secs());
+            }
+        }
+
+        if let Some(idle_timeout) = ctx.idle_timeout {
+            let current_log_len = log_len(&log_path);
+            if current_log_len != last_log_len {
+                last_log_len = current_log_len;
+                if let Ok(mut last) = last_activity.lock() {
+                    *last = now;
+                }
+            }
+            let idle = last_activity
+                .lock()
+                .map(|last| now.duration_since(*last))
+                .unwrap_or_default();
+            if idle >= idle_timeout {
+                return format!(
+                    "{} produced no output for {} seconds and was stopped",
+                    program,
+                    idle_timeout.as_secs()
+                );
+            }
+        }
+    }
+}
+
+fn emit_compile_progress(ctx: &BuildContext<'_>, pass: u32, tool: &str, message: String) {
+    let _ = ctx.app.emit(
+        "latex-compile-progress",
+        CompileProgressEvent {
+            job_id: ctx.job.job_id.clone(),
+            pass,
+            max_passes: MAX_COMPILE_PASSES,
+            tool: tool.to_string(),
+            message,
+        },
+    );
+}
+
+/// Pick the engine latexmk would have used from its command-line switches.
+fn latexmk_fallback_engine(args: &[String]) -> &'static str {
+    if args.iter().any(|a| a == "-xelatex" || a == "-pdfxe") {
+        "xelatex"
+    } else if args.iter().any(|a| a == "-lualatex" || a == "-pdflua") {
+        "lualatex"
+    } else {
+        "pdflatex"
+    }
+}
+
+/// Search path value that puts the project directory ahead of the TeX defaults
+fn project_search_path(directory: &str) -> String {
+    let separator = if cfg!(target_os = "windows") {
+        ";"
+    } else {
+        ":"
+    };
+    format!("{}{}", directory, separator)
+}
+
+/// Run the engine until the document is stable, calling bibtex/biber and makeindex
+/// in between. This is what latexmk would do for us when it is installed.
+async fn run_build_passes(
+    ctx: &BuildContext<'_>,
+    engine: &str,
+    engine_path: &str,
+    engine_args: &[String],
+) -> Result<Option<i32>, String> {
+    let aux_dir = ctx.dirs.aux.as_path();
+    let aux_dir_str = aux_dir.to_string_lossy().to_string();
+    let job_name = ctx.job_name.as_str();
+    let mut aux_digest = aux_state_digest(aux_dir, job_name);
+    let mut bib_input_digest: Option<u64> = None;
+    let mut index_input_digest: Option<u64> = None;
+    let mut pass = 0;
+
+    // Helper tools run inside the aux directory but must find the project's
+    // .bib, .bst and .ist files
+    let search_path = project_search_path(ctx.directory);
+    let tool_envs = [
+        ("BIBINPUTS", search_path.clone()),
+        ("BSTINPUTS", search_path.clone()),
+        ("INDEXSTYLE", search_path),
+    ];
+
+    loop {
+        pass += 1;
+        emit_compile_progress(
+            ctx,
+            pass,
+            engine,
+            format!("Running {} (pass {})", engine, pass),
+        );
+        let exit_code = run_compile_step(ctx, engine_path, engine_args, ctx.directory, &[]).await?;
+        if exit_code != Some(0) || pass >= MAX_COMPILE_PASSES {
+            return Ok(exit_code);
+        }
+
+        let mut needs_rerun = read_log_diagnostics(&ctx.dirs.log(job_name).to_string_lossy())
+            .iter()
+            .any(|d| d.kind == "rerun");
+
+        // Bibliography: rerun the engine only if the .bbl actually changed
+        if let Some(tool) = detect_bib_tool(aux_dir, job_name) {
+            let input_digest = bib_tool_input_digest(aux_dir, job_name, tool);
+            if bib_input_digest != Some(input_digest) {
+                bib_input_digest = Some(input_digest);
+                let bbl_path = aux_dir.join(format!("{}.bbl", job_name));
+                let before = file_digest(&bbl_path);
+                emit_compile_progress(ctx, pass, tool, format!("Running {}", tool));
+                let tool_path = resolve_tex_tool(tool, engine_path).await;
+                let tool_args = if tool == "biber" {
+                    vec![
+                        "--input-directory".to_string(),
+                        aux_dir_str.clone(),
+                        "--output-directory".to_string(),
+                        aux_dir_str.clone(),
+                        job_name.to_string(),
+                    ]
+                } else {
+                    vec![job_name.to_string()]
+                };
+                let tool_cwd = if tool == "biber" {
+                    ctx.directory
+                } else {
+                    aux_dir_str.as_str()
+                };
+                let tool_exit =
+                    run_compile_step(ctx, &tool_path, &tool_args, tool_cwd, &tool_envs).await?;
+                if ctx.timeout_reason().is_some() {
+                    return Ok(None);
                 }
-                Err(e) => {
-                    return Err(format!("Failed to wait for compiler: {}", e));
+                if tool_exit != Some(0) {
+                    emit_compile_progress(
+                        ctx,
+                        pass,
+                        tool,
+                        format!("{} finished with exit code {:?}", tool, tool_exit),
+                    );
                 }
+                needs_rerun |= file_digest(&bbl_path) != before;
+            }
+        }
+
+        // Index: same idea with makeindex and the .ind file
+        let idx_path = aux_dir.join(format!("{}.idx", job_name));
+        if let Some(input_digest) = file_digest(&idx_path) {
+            if index_input_digest != Some(input_digest) {
+                index_input_digest = Some(input_digest);
+                let ind_path = aux_dir.join(format!("{}.ind", job_name));
+                let before = file_digest(&ind_path);
+                emit_compile_progress(ctx, pass, "makeindex", "Running makeindex".to_string());
+                let tool_path = resolve_tex_tool("makeindex", engine_path).await;
+                run_compile_step(
+                    ctx,
+                    &tool_path,
+                    &[format!("{}.idx", job_name)],
+                    &aux_dir_str,
+                    &tool_envs,
+                )
+                .await?;
+                if ctx.timeout_reason().is_some() {
+                    return Ok(None);
+                }
+                needs_rerun |= file_digest(&ind_path) != before;
             }
-        } else {
-            return Err("Process was terminated".to_string());
+        }
+
+        // Cross-references are stable once the auxiliary files stop changing
+        let new_aux_digest = aux_state_digest(aux_dir, job_name);
+        needs_rerun |= new_aux_digest != aux_digest;
+        aux_digest = new_aux_digest;
+
+        if !needs_rerun {
+            return Ok(exit_code);
         }
     }
+}
 
-    // Determine the PDF path
-    let pdf_name = main_file.strip_suffix(".tex").unwrap_or(&main_file);
-    let pdf_path = format!("{}/{}.pdf", directory, pdf_name);
+/// Locate a helper tool, preferring the one installed next to the engine.
+async fn resolve_tex_tool(name: &str, engine_path: &str) -> String {
+    let exe_name = if cfg!(target_os = "windows") {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    };
+    if let Some(parent) = std::path::Path::new(engine_path).parent() {
+        let sibling = parent.join(&exe_name);
+        if parent.is_absolute() && sibling.exists() {
+            return sibling.to_string_lossy().to_string();
+        }
+    }
+    find_compiler(name)
+        .await
+        .path
+        .unwrap_or_else(|| name.to_string())
+}
 
-    let pdf_exists = std::path::Path::new(&pdf_path).exists();
+/// Which bibliography tool the last pass asked for, if any.
+fn detect_bib_tool(dir: &std::path::Path, job_name: &str) -> Option<&'static str> {
+    if dir.join(format!("{}.bcf", job_name)).exists() {
+        return Some("biber");
+    }
+    let aux = std::fs::read_to_string(dir.join(format!("{}.aux", job_name))).ok()?;
+    if aux.contains("\\bibdata{") {
+        Some("bibtex")
+    } else {
+        None
+    }
+}
 
-    let success = exit_code == Some(0) && pdf_exists;
+/// Digest of everything the bibliography tool reads from the build.
+fn bib_tool_input_digest(dir: &std::path::Path, job_name: &str, tool: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
 
-    Ok(CompilationResult {
-        success,
-        exit_code,
-        pdf_path: if pdf_exists { Some(pdf_path) } else { None },
-        error: if !success && !pdf_exists {
-            Some("PDF was not generated".to_string())
-        } else if !success {
-            Some(format!("Compilation failed with exit code {:?}", exit_code))
-        } else {
-            None
-        },
-    })
+    if tool == "biber" {
+        return file_digest(&dir.join(format!("{}.bcf", job_name))).unwrap_or(0);
+    }
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for aux_path in aux_file_set(dir, job_name) {
+        if let Ok(content) = std::fs::read_to_string(&aux_path) {
+            for line in content.lines() {
+                if line.starts_with("\\citation{")
+                    || line.starts_with("\\bibdata{")
+                    || line.starts_with("\\bibstyle{")
+                {
+                    line.hash(&mut hasher);
+                }
+            }
+        }
+    }
+    hasher.finish()
 }
 
+/// Digest of the auxiliary files whose changes require another engine pass.
+fn aux_state_digest(dir: &std::path::Path, job_name: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let mut paths = aux_file_set(dir, job_name);
+    for ext in ["toc", "lof", "lot"] {
+        paths.push(dir.join(format!("{}.{}", job_name, ext)));
+    }
+    for path in paths {
+        file_digest(&path).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// The main .aux file plus the per-file .aux files it pulls in via `\@input`.
+fn aux_file_set(dir: &std::path::Path, job_name: &str) -> Vec<std::path::PathBuf> {
+    let main_aux = dir.join(format!("{}.aux", job_name));
+    let mut paths = vec![main_aux.clone()];
+    if let Ok(content) = std::fs::read_to_string(&main_aux) {
+        for line in content.lines() {
+            if let Some(rest) = line.strip_prefix("\\@input{") {
+                if let Some(name) = rest.strip_suffix('}') {
+                    paths.push(dir.join(name));
+                }
+            }
+        }
+    }
+    paths
+}
+
+fn file_digest(path: &std::path::Path) -> Option<u64> {
+    use std::hash::{Hash, Hasher};
+
+    let bytes = std::fs::read(path).ok()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Read and parse a TeX log file. Logs are not guaranteed to be UTF-8.
+fn read_log_diagnostics(log_path: &str) -> Vec<LaTeXDiagnostic> {
+    match std::fs::read(log_path) {
+        Ok(bytes) => log_parser::parse_log(&String::from_utf8_lossy(&bytes)),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Stop every queued and running compile job
 #[tauri::command]
 pub async fn latex_stop_compilation(state: State<'_, LaTeXCompilationState>) -> Result<(), String> {
-    let mut process_guard = state.current_process.lock().await;
-    if let Some(mut child) = process_guard.take() {
-        child
-            .kill()
-            .await
-            .map_err(|e| format!("Failed to stop compilation: {}", e))?;
+    state.jobs.cancel_all().await;
+    Ok(())
+}
+
+/// Cancel a single compile job by id
+#[tauri::command]
+pub async fn latex_cancel_job(
+    state: State<'_, LaTeXCompilationState>,
+    job_id: String,
+) -> Result<(), String> {
+    if state.jobs.cancel(&job_id).await {
+        Ok(())
+    } else {
+        Err(format!("No compile job with id {}", job_id))
+    }
+}
+
+/// List queued and running compile jobs
+#[tauri::command]
+pub async fn latex_list_jobs(
+    state: State<'_, LaTeXCompilationState>,
+) -> Result<Vec<CompileJobInfo>, String> {
+    Ok(state.jobs.list().await)
+}
+
+/// Set how many compile jobs may run in parallel; further jobs wait in a queue
+#[tauri::command]
+pub async fn latex_set_max_parallel_jobs(
+    state: State<'_, LaTeXCompilationState>,
+    limit: usize,
+) -> Result<(), String> {
+    if limit == 0 {
+        return Err("At least one compile job must be allowed to run".to_string());
+    }
+    state.jobs.set_max_parallel(limit);
+    Ok(())
+}
+
+/// Rebuild a document whenever one of its sources changes. Returns the watch's job id.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn latex_watch_start(
+    app: AppHandle,
+    state: State<'_, LaTeXCompilationState>,
+    directory: String,
+    compiler: String,
+    main_file: String,
+    args: Vec<String>,
+    custom_path: Option<String>,
+    options: Option<CompileOptions>,
+) -> Result<String, String> {
+    let request = CompileRequest {
+        directory,
+        compiler,
+        main_file,
+        args,
+        custom_path,
+        options: options.unwrap_or_default(),
+    };
+    state.watches.start(&app, request)
+}
+
+/// Stop watching one document, or every document when no job id is given
+#[tauri::command]
+pub async fn latex_watch_stop(
+    app: AppHandle,
+    state: State<'_, LaTeXCompilationState>,
+    job_id: Option<String>,
+) -> Result<(), String> {
+    for stopped in state.watches.stop(job_id.as_deref())? {
+        state.jobs.cancel(&stopped).await;
+        watch::emit_watch_status(&app, &stopped, "stopped", Vec::new(), None);
     }
     Ok(())
 }
 
 #[tauri::command]
-pub async fn latex_clean_aux_files(directory: String, main_file: String) -> Result<(), String> {
+pub async fn latex_clean_aux_files(
+    directory: String,
+    main_file: String,
+    output_directory: Option<String>,
+    aux_directory: Option<String>,
+) -> Result<(), String> {
     let base_name = main_file.strip_suffix(".tex").unwrap_or(&main_file);
+    let job_name = std::path::Path::new(base_name)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| base_name.to_string());
+    let dirs = BuildDirs::resolve(
+        &directory,
+        output_directory.as_deref(),
+        aux_directory.as_deref(),
+    );
+
+    // Next to the main file, plus wherever the build put its products
+    let mut stems = vec![std::path::Path::new(&directory).join(base_name)];
+    for dir in [&dirs.output, &dirs.aux] {
+        let stem = dir.join(&job_name);
+        if !stems.contains(&stem) {
+            stems.push(stem);
+        }
+    }
 
     let aux_extensions = [
         ".aux",
@@ -471,10 +1334,12 @@ pub async fn latex_clean_aux_files(directory: String, main_file: String) -> Resu
         ".vrb",
     ];
 
-    for ext in &aux_extensions {
-        let file_path = format!("{}/{}{}", directory, base_name, ext);
-        if std::path::Path::new(&file_path).exists() {
-            let _ = std::fs::remove_file(&file_path);
+    for stem in &stems {
+        for ext in &aux_extensions {
+            let file_path = format!("{}{}", stem.to_string_lossy(), ext);
+            if std::path::Path::new(&file_path).exists() {
+                let _ = std::fs::remove_file(&file_path);
+            }
         }
     }
 
@@ -494,6 +1359,7 @@ pub async fn latex_synctex_edit(
     page: u32,
     x: f64,
     y: f64,
+    synctex_path: Option<String>,
 ) -> Result<SynctexResult, String> {
     // Find the synctex binary using the same logic as compiler detection
     let synctex_info = find_compiler("synctex").await;
@@ -507,6 +1373,14 @@ pub async fn latex_synctex_edit(
     let mut cmd = command(&synctex_bin);
     cmd.arg("edit").arg("-o").arg(&input_spec);
 
+    // Builds with an aux directory may keep the SyncTeX file away from the PDF
+    if let Some(dir) = synctex_path
+        .as_deref()
+        .and_then(|p| std::path::Path::new(p).parent())
+    {
+        cmd.arg("-d").arg(dir);
+    }
+
     // Ensure PATH includes common TeX directories
     let env_path = std::env::var("PATH").unwrap_or_default();
     #[cfg(target_os = "macos")]
@@ -1460,3 +2334,104 @@ pub async fn latex_detect_main_file(
         ),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_latexmk_fallback_engine_follows_latexmk_switches() {
+        assert_eq!(
+            latexmk_fallback_engine(&["-xelatex".to_string()]),
+            "xelatex"
+        );
+        assert_eq!(
+            latexmk_fallback_engine(&["-pdflua".to_string()]),
+            "lualatex"
+        );
+        assert_eq!(latexmk_fallback_engine(&["-pdf".to_string()]), "pdflatex");
+        assert_eq!(latexmk_fallback_engine(&[]), "pdflatex");
+    }
+
+    #[test]
+    fn test_build_dirs_resolve_relative_to_project() {
+        let dirs = BuildDirs::resolve("/work/paper", Some("build"), None);
+        assert_eq!(dirs.output, std::path::Path::new("/work/paper/build"));
+        assert_eq!(dirs.aux, dirs.output);
+        assert_eq!(
+            dirs.pdf("main"),
+            std::path::Path::new("/work/paper/build/main.pdf")
+        );
+
+        let dirs = BuildDirs::resolve("/work/paper", None, Some("aux"));
+        assert_eq!(dirs.output, std::path::Path::new("/work/paper"));
+        assert_eq!(dirs.aux, std::path::Path::new("/work/paper/aux"));
+    }
+
+    #[test]
+    fn test_prepare_build_dirs_mirrors_project_subdirectories() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        fs::create_dir_all(project.join("chapters/appendix")).unwrap();
+        fs::create_dir_all(project.join("node_modules/pkg")).unwrap();
+
+        let dirs = BuildDirs::resolve(&project.to_string_lossy(), Some("_build"), None);
+        prepare_build_dirs(&project.to_string_lossy(), &dirs).unwrap();
+
+        assert!(project.join("_build/chapters/appendix").is_dir());
+        assert!(!project.join("_build/node_modules").exists());
+        assert!(!project.join("_build/_build").exists());
+    }
+
+    #[test]
+    fn test_read_log_tail_falls_back_to_tool_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_path = temp_dir.path().join("main.log");
+        let recent_output = StdMutex::new(VecDeque::from(vec!["[1] (./main.tex".to_string()]));
+
+        assert_eq!(
+            read_log_tail(&log_path, &recent_output),
+            vec!["[1] (./main.tex"]
+        );
+
+        let log: Vec<String> = (1..=30).map(|n| format!("line {}", n)).collect();
+        fs::write(&log_path, log.join("\n")).unwrap();
+        let tail = read_log_tail(&log_path, &recent_output);
+        assert_eq!(tail.len(), LOG_TAIL_LINES);
+        assert_eq!(tail.last().map(String::as_str), Some("line 30"));
+    }
+
+    #[test]
+    fn test_detect_bib_tool_reads_aux_and_bcf() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+
+        assert_eq!(detect_bib_tool(dir, "main"), None);
+
+        fs::write(
+            dir.join("main.aux"),
+            "\\relax\n\\citation{knuth}\n\\bibdata{refs}\n",
+        )
+        .unwrap();
+        assert_eq!(detect_bib_tool(dir, "main"), Some("bibtex"));
+
+        fs::write(dir.join("main.bcf"), "<bcf:controlfile/>").unwrap();
+        assert_eq!(detect_bib_tool(dir, "main"), Some("biber"));
+    }
+
+    #[test]
+    fn test_aux_digest_tracks_included_aux_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("main.aux"), "\\relax\n\\@input{intro.aux}\n").unwrap();
+        fs::write(dir.join("intro.aux"), "\\newlabel{sec:a}{{1}{1}}\n").unwrap();
+
+        let before = aux_state_digest(dir, "main");
+        assert_eq!(before, aux_state_digest(dir, "main"));
+
+        fs::write(dir.join("intro.aux"), "\\newlabel{sec:a}{{2}{1}}\n").unwrap();
+        assert_ne!(before, aux_state_digest(dir, "main"));
+    }
+}
@@ -0,0 +1,282 @@
+use crate::commands::util::command;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::process::Child;
+use tokio::sync::Mutex;
+
+/// Number of compile jobs allowed to run at the same time unless configured otherwise
+pub const DEFAULT_MAX_PARALLEL_JOBS: usize = 2;
+
+const QUEUE_POLL_MS: u64 = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileJobInfo {
+    pub job_id: String,
+    pub directory: String,
+    pub main_file: String,
+    pub compiler: String,
+    /// "queued" or "running"
+    pub status: String,
+    /// Unix time in milliseconds when the job was submitted
+    pub submitted_at: u64,
+}
+
+/// Handle shared between a running compile and whoever may cancel it.
+#[derive(Clone)]
+pub struct CompileJobHandle {
+    pub job_id: String,
+    run_id: String,
+    pub process: Arc<Mutex<Option<Child>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CompileJobHandle {
+    fn new(job_id: String) -> Self {
+        Self {
+            job_id,
+            run_id: uuid::Uuid::new_v4().to_string(),
+            process: Arc::new(Mutex::new(None)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Mark the job as cancelled and kill its current process, if any.
+    pub async fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        let mut process_guard = self.process.lock().await;
+        if let Some(mut child) = process_guard.take() {
+            kill_process_tree(&mut child).await;
+        }
+    }
+}
+
+/// Kill a build tool together with everything it started (latexmk's engine runs,
+/// an engine's shell-escape commands). On Unix the tool leads its own process group.
+pub async fn kill_process_tree(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        #[cfg(unix)]
+        let _ = command("kill")
+            .args(["-9", &format!("-{}", pid)])
+            .output()
+            .await;
+        #[cfg(windows)]
+        let _ = command("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .output()
+            .await;
+    }
+    let _ = child.kill().await;
+}
+
+struct CompileJob {
+    info: CompileJobInfo,
+    handle: CompileJobHandle,
+}
+
+/// Table of queued and running compile jobs, keyed by job id.
+pub struct CompileJobs {
+    jobs: Mutex<HashMap<String, CompileJob>>,
+    max_parallel: AtomicUsize,
+}
+
+impl Default for CompileJobs {
+    fn default() -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            max_parallel: AtomicUsize::new(DEFAULT_MAX_PARALLEL_JOBS),
+        }
+    }
+}
+
+impl CompileJobs {
+    /// Register a new run for `info.job_id`, cancelling any run already using that id.
+    pub async fn submit(&self, mut info: CompileJobInfo) -> CompileJobHandle {
+        info.status = "queued".to_string();
+        info.submitted_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let handle = CompileJobHandle::new(info.job_id.clone());
+        let previous = {
+            let mut jobs = self.jobs.lock().await;
+            jobs.insert(
+                info.job_id.clone(),
+                CompileJob {
+                    info,
+                    handle: handle.clone(),
+                },
+            )
+        };
+        if let Some(previous) = previous {
+            previous.handle.cancel().await;
+        }
+        handle
+    }
+
+    /// Wait in the queue until fewer than the configured number of jobs are running.
+    pub async fn wait_for_slot(&self, handle: &CompileJobHandle) -> Result<(), String> {
+        loop {
+            if handle.is_cancelled() {
+                return Err("Compilation was cancelled".to_string());
+            }
+            {
+                let mut jobs = self.jobs.lock().await;
+                let running = jobs.values().filter(|j| j.info.status == "running").count();
+                if running < self.max_parallel.load(Ordering::SeqCst) {
+                    if let Some(job) = jobs.get_mut(&handle.job_id) {
+                        if job.handle.run_id == handle.run_id {
+                            job.info.status = "running".to_string();
+                        }
+                    }
+                    return Ok(());
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(QUEUE_POLL_MS)).await;
+        }
+    }
+
+    /// Remove a finished run, unless a newer run has taken over its id.
+    pub async fn finish(&self, handle: &CompileJobHandle) {
+        let mut jobs = self.jobs.lock().await;
+        if jobs
+            .get(&handle.job_id)
+            .is_some_and(|job| job.handle.run_id == handle.run_id)
+        {
+            jobs.remove(&handle.job_id);
+        }
+    }
+
+    pub async fn list(&self) -> Vec<CompileJobInfo> {
+        let jobs = self.jobs.lock().await;
+        let mut infos: Vec<CompileJobInfo> = jobs.values().map(|j| j.info.clone()).collect();
+        infos.sort_by_key(|info| info.submitted_at);
+        infos
+    }
+
+    /// Cancel one job. Returns false if no job with that id exists.
+    pub async fn cancel(&self, job_id: &str) -> bool {
+        let job = {
+            let mut jobs = self.jobs.lock().await;
+            jobs.remove(job_id)
+        };
+        match job {
+            Some(job) => {
+                job.handle.cancel().await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn cancel_all(&self) {
+        let drained: Vec<CompileJob> = {
+            let mut jobs = self.jobs.lock().await;
+            jobs.drain().map(|(_, job)| job).collect()
+        };
+        for job in drained {
+            job.handle.cancel().await;
+        }
+    }
+
+    pub fn set_max_parallel(&self, limit: usize) {
+        self.max_parallel.store(limit, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn job_status(jobs: &CompileJobs, job_id: &str) -> String {
+        jobs.list()
+            .await
+            .into_iter()
+            .find(|info| info.job_id == job_id)
+            .map(|info| info.status)
+            .unwrap_or_default()
+    }
+
+    fn job_info(job_id: &str) -> CompileJobInfo {
+        CompileJobInfo {
+            job_id: job_id.to_string(),
+            directory: "/tmp/project".to_string(),
+            main_file: "main.tex".to_string(),
+            compiler: "pdflatex".to_string(),
+            status: String::new(),
+            submitted_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_same_id_cancels_previous_run() {
+        let jobs = CompileJobs::default();
+        let first = jobs.submit(job_info("paper")).await;
+        let second = jobs.submit(job_info("paper")).await;
+
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        // The cancelled run must not remove the new run's entry
+        jobs.finish(&first).await;
+        assert_eq!(jobs.list().await.len(), 1);
+        jobs.finish(&second).await;
+        assert!(jobs.list().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_slot_respects_parallel_limit() {
+        let jobs = CompileJobs::default();
+        jobs.set_max_parallel(1);
+        let paper = jobs.submit(job_info("paper")).await;
+        let rebuttal = jobs.submit(job_info("rebuttal")).await;
+
+        jobs.wait_for_slot(&paper).await.unwrap();
+        let queued = tokio::time::timeout(
+            Duration::from_millis(3 * QUEUE_POLL_MS),
+            jobs.wait_for_slot(&rebuttal),
+        )
+        .await;
+        assert!(queued.is_err(), "second job should still be queued");
+        assert_eq!(job_status(&jobs, "rebuttal").await, "queued");
+
+        jobs.finish(&paper).await;
+        jobs.wait_for_slot(&rebuttal).await.unwrap();
+        assert_eq!(job_status(&jobs, "rebuttal").await, "running");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_kill_process_tree_kills_grandchildren() {
+        let marker = tempfile::NamedTempFile::new().unwrap();
+        let marker_path = marker.path().to_string_lossy().to_string();
+        let mut child = command("sh")
+            .args([
+                "-c",
+                &format!("(sleep 1; echo alive > {}) & wait", marker_path),
+            ])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+
+        kill_process_tree(&mut child).await;
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(std::fs::read_to_string(&marker_path).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_job_leaves_queue() {
+        let jobs = CompileJobs::default();
+        let handle = jobs.submit(job_info("paper")).await;
+        assert!(jobs.cancel("paper").await);
+        assert!(jobs.wait_for_slot(&handle).await.is_err());
+        assert!(!jobs.cancel("paper").await);
+    }
+}
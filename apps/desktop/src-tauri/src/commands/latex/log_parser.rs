@@ -0,0 +1,485 @@
+use serde::{Deserialize, Serialize};
+
+/// TeX hard-wraps log lines at `max_print_line` characters (79 by default)
+const LOG_WRAP_LIMIT: usize = 79;
+/// Upper bound on context lines attached to a single diagnostic
+const MAX_CONTEXT_LINES: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaTeXDiagnostic {
+    /// "error", "warning" or "info" (bad boxes)
+    pub severity: String,
+    /// "general", "missing_file", "missing_font", "undefined_reference",
+    /// "undefined_citation", "bad_box" or "rerun"
+    pub kind: String,
+    /// Source file the message belongs to, if it could be determined
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+    /// Log lines that followed the message (TeX's error context)
+    pub context: Vec<String>,
+}
+
+/// Parse the contents of a TeX `.log` file into diagnostics.
+pub fn parse_log(content: &str) -> Vec<LaTeXDiagnostic> {
+    LogParser::new(content).parse()
+}
+
+/// Classify a single line of streamed compiler output as (is_error, is_warning).
+pub fn classify_line(line: &str) -> (bool, bool) {
+    let is_error = line.starts_with("! ") || line == "!" || split_file_line_error(line).is_some();
+    let is_warning = !is_error
+        && (warning_header(line).is_some()
+            || line.starts_with("Overfull \\")
+            || line.starts_with("Underfull \\"));
+    (is_error, is_warning)
+}
+
+struct LogParser {
+    lines: Vec<String>,
+    pos: usize,
+    /// Files opened with `(` in the log; `None` marks a non-file parenthesis
+    file_stack: Vec<Option<String>>,
+    diagnostics: Vec<LaTeXDiagnostic>,
+}
+
+impl LogParser {
+    fn new(content: &str) -> Self {
+        Self {
+            lines: unwrap_lines(content),
+            pos: 0,
+            file_stack: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn parse(mut self) -> Vec<LaTeXDiagnostic> {
+        while self.pos < self.lines.len() {
+            let line = self.lines[self.pos].clone();
+            self.pos += 1;
+
+            if let Some((file, line_no, message)) = split_file_line_error(&line) {
+                self.push_error(Some(file), Some(line_no), message);
+            } else if let Some(message) = line.strip_prefix("! ") {
+                let file = self.current_file();
+                self.push_error(file, None, message.to_string());
+            } else if let Some(prefix_len) = warning_header(&line) {
+                self.push_warning(&line, prefix_len);
+            } else if line.starts_with("Overfull \\") || line.starts_with("Underfull \\") {
+                self.push_bad_box(&line);
+            } else {
+                self.scan_files(&line);
+            }
+        }
+
+        self.diagnostics
+    }
+
+    fn current_file(&self) -> Option<String> {
+        self.file_stack.iter().rev().find_map(|f| f.clone())
+    }
+
+    fn push_error(&mut self, file: Option<String>, line: Option<u32>, message: String) {
+        let mut line = line;
+        let mut context = Vec::new();
+
+        // TeX reports the offending input line as "l.<n> ..." a few lines below the
+        // message; everything in between is context.
+        let lookahead_end = (self.pos + 20).min(self.lines.len());
+        let location = (self.pos..lookahead_end)
+            .take_while(|&i| !is_message_start(&self.lines[i]))
+            .find_map(|i| parse_error_line_number(&self.lines[i]).map(|n| (i, n)));
+
+        if let Some((index, line_no)) = location {
+            context.extend(
+                self.lines[self.pos..=index]
+                    .iter()
+                    .filter(|l| !l.trim().is_empty())
+                    .cloned(),
+            );
+            self.pos = index + 1;
+            // The remainder of the offending input line is printed on the next line
+            if let Some(next) = self.lines.get(self.pos) {
+                if next.starts_with(' ') && !next.trim().is_empty() {
+                    context.push(next.clone());
+                    self.pos += 1;
+                }
+            }
+            line = line.or(Some(line_no));
+        } else {
+            while self.pos < self.lines.len() && context.len() < MAX_CONTEXT_LINES {
+                let next = &self.lines[self.pos];
+                if next.trim().is_empty() || is_message_start(next) {
+                    break;
+                }
+                context.push(next.clone());
+                self.pos += 1;
+            }
+        }
+        context.truncate(MAX_CONTEXT_LINES);
+
+        let kind = if message.contains("not found") && message.contains("File `") {
+            "missing_file"
+        } else if message.contains("Metric (TFM) file") || message.contains("not loadable") {
+            "missing_font"
+        } else {
+            "general"
+        };
+
+        self.diagnostics.push(LaTeXDiagnostic {
+            severity: "error".to_string(),
+            kind: kind.to_string(),
+            file,
+            line,
+            message,
+            context,
+        });
+    }
+
+    fn push_warning(&mut self, header: &str, prefix_len: usize) {
+        let mut message = header[prefix_len..].trim().to_string();
+        let single_line = header.starts_with("pdfTeX warning");
+
+        // Multi-line warnings continue until a blank line; package warnings prefix
+        // continuation lines with "(pkgname)".
+        let mut consumed = 0;
+        while !single_line && self.pos < self.lines.len() && consumed < MAX_CONTEXT_LINES {
+            let next = &self.lines[self.pos];
+            if next.trim().is_empty() || is_message_start(next) {
+                break;
+            }
+            if next.starts_with('(') && continuation_prefix_len(next).is_none() {
+                break;
+            }
+            let text = &next[continuation_prefix_len(next).unwrap_or(0)..];
+            message.push(' ');
+            message.push_str(text.trim());
+            self.pos += 1;
+            consumed += 1;
+        }
+
+        let kind = if message.contains("Rerun to get")
+            || message.contains("may have changed")
+            || message.contains("rerun LaTeX")
+        {
+            "rerun"
+        } else if message.starts_with("Reference `") && message.contains("undefined") {
+            "undefined_reference"
+        } else if message.starts_with("Citation `") && message.contains("undefined") {
+            "undefined_citation"
+        } else {
+            "general"
+        };
+
+        let line = number_after(&message, "on input line ");
+        let file = self.current_file();
+        self.diagnostics.push(LaTeXDiagnostic {
+            severity: "warning".to_string(),
+            kind: kind.to_string(),
+            file,
+            line,
+            message,
+            context: Vec::new(),
+        });
+    }
+
+    fn push_bad_box(&mut self, header: &str) {
+        let line = number_after(header, "at lines ").or_else(|| number_after(header, "at line "));
+
+        // The offending box content follows until the next blank line
+        let mut context = Vec::new();
+        while self.pos < self.lines.len() && context.len() < MAX_CONTEXT_LINES {
+            let next = &self.lines[self.pos];
+            if next.trim().is_empty() || is_message_start(next) {
+                break;
+            }
+            context.push(next.clone());
+            self.pos += 1;
+        }
+
+        let file = self.current_file();
+        self.diagnostics.push(LaTeXDiagnostic {
+            severity: "info".to_string(),
+            kind: "bad_box".to_string(),
+            file,
+            line,
+            message: header.trim().to_string(),
+            context,
+        });
+    }
+
+    /// Track `(file` / `)` pairs so messages can be attributed to a source file.
+    fn scan_files(&mut self, line: &str) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '(' => {
+                    let (token, next) = read_file_token(&chars, i + 1);
+                    if looks_like_file(&token) {
+                        self.file_stack.push(Some(normalize_log_path(&token)));
+                    } else {
+                        self.file_stack.push(None);
+                    }
+                    i = next;
+                }
+                ')' => {
+                    self.file_stack.pop();
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+}
+
+/// Join lines that TeX hard-wrapped at the log width.
+fn unwrap_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut previous_wrapped = false;
+    for raw in content.lines() {
+        let raw = raw.trim_end_matches('\r');
+        if previous_wrapped {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(raw);
+            }
+        } else {
+            lines.push(raw.to_string());
+        }
+        previous_wrapped = raw.len() == LOG_WRAP_LIMIT && !raw.ends_with("...");
+    }
+    lines
+}
+
+/// Split a `-file-line-error` style message: `./chapters/intro.tex:12: message`.
+fn split_file_line_error(line: &str) -> Option<(String, u32, String)> {
+    for (index, _) in line.match_indices(':') {
+        let rest = &line[index + 1..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.is_empty() || !rest[digits.len()..].starts_with(": ") {
+            continue;
+        }
+        let file = &line[..index];
+        if !has_file_extension(file) {
+            continue;
+        }
+        let line_no = digits.parse().ok()?;
+        let message = rest[digits.len() + 2..].trim().to_string();
+        return Some((normalize_log_path(file), line_no, message));
+    }
+    None
+}
+
+/// Returns the length of the warning prefix if the line starts a warning.
+fn warning_header(line: &str) -> Option<usize> {
+    for prefix in ["LaTeX Warning: ", "LaTeX Font Warning: ", "pdfTeX warning"] {
+        if line.starts_with(prefix) {
+            return Some(prefix.len());
+        }
+    }
+    for kind in ["Package ", "Class "] {
+        if let Some(rest) = line.strip_prefix(kind) {
+            let name_len = rest.find(' ')?;
+            if rest[name_len..].starts_with(" Warning: ") {
+                return Some(kind.len() + name_len + " Warning: ".len());
+            }
+        }
+    }
+    None
+}
+
+fn is_message_start(line: &str) -> bool {
+    line.starts_with('!')
+        || split_file_line_error(line).is_some()
+        || warning_header(line).is_some()
+        || line.starts_with("Overfull \\")
+        || line.starts_with("Underfull \\")
+}
+
+/// Length of a `(pkgname)   ` continuation prefix, if present.
+fn continuation_prefix_len(line: &str) -> Option<usize> {
+    let close = line.find(')')?;
+    let name = &line[1..close];
+    if !line.starts_with('(') || name.is_empty() || name.contains(['.', '/', '\\', ' ']) {
+        return None;
+    }
+    let rest = &line[close + 1..];
+    if !rest.starts_with(' ') {
+        return None;
+    }
+    Some(close + 1 + (rest.len() - rest.trim_start().len()))
+}
+
+fn parse_error_line_number(line: &str) -> Option<u32> {
+    let rest = line.strip_prefix("l.")?;
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+fn number_after(text: &str, marker: &str) -> Option<u32> {
+    let start = text.find(marker)? + marker.len();
+    let digits: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn read_file_token(chars: &[char], start: usize) -> (String, usize) {
+    let mut i = start;
+    let mut token = String::new();
+
+    // XeTeX and LuaTeX quote paths that contain spaces
+    if chars.get(i) == Some(&'"') {
+        i += 1;
+        while i < chars.len() && chars[i] != '"' {
+            token.push(chars[i]);
+            i += 1;
+        }
+        return (token, (i + 1).min(chars.len()));
+    }
+
+    while i < chars.len() && !chars[i].is_whitespace() && !matches!(chars[i], '(' | ')' | '[' | '{')
+    {
+        token.push(chars[i]);
+        i += 1;
+    }
+    (token, i)
+}
+
+fn has_file_extension(path: &str) -> bool {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match name.rfind('.') {
+        Some(dot) if dot + 1 < name.len() => {
+            let ext = &name[dot + 1..];
+            ext.starts_with(|c: char| c.is_ascii_alphabetic())
+                && ext
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }
+        _ => false,
+    }
+}
+
+fn looks_like_file(token: &str) -> bool {
+    !token.is_empty() && has_file_extension(token)
+}
+
+fn normalize_log_path(path: &str) -> String {
+    path.strip_prefix("./")
+        .or_else(|| path.strip_prefix(".\\"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_tracks_file_stack_for_errors() {
+        let log = "This is pdfTeX, Version 3.141592653\n\
+(./main.tex\n\
+LaTeX2e <2023-11-01>\n\
+(/usr/share/texmf/tex/latex/base/article.cls\n\
+Document Class: article 2023/05/17 v1.4n Standard LaTeX document class\n\
+(/usr/share/texmf/tex/latex/base/size10.clo))\n\
+(./chapters/intro.tex\n\
+! Undefined control sequence.\n\
+l.12 \\foo\n\
+\x20         bar\n\
+\n\
+) [1] (./main.aux) )\n";
+
+        let diagnostics = parse_log(log);
+        assert_eq!(diagnostics.len(), 1);
+        let error = &diagnostics[0];
+        assert_eq!(error.severity, "error");
+        assert_eq!(error.file.as_deref(), Some("chapters/intro.tex"));
+        assert_eq!(error.line, Some(12));
+        assert_eq!(error.message, "Undefined control sequence.");
+        assert_eq!(error.context, vec!["l.12 \\foo", "          bar"]);
+    }
+
+    #[test]
+    fn test_parse_log_ignores_error_word_in_file_names() {
+        let log = "(./main.tex (./error-analysis.tex) (./warnings.tex))\n";
+        assert!(parse_log(log).is_empty());
+    }
+
+    #[test]
+    fn test_parse_log_reads_file_line_error_and_missing_file() {
+        let log = "(./main.tex\n\
+./main.tex:3: LaTeX Error: File `missing.sty' not found.\n\
+\n\
+Type X to quit or <RETURN> to proceed,\n";
+
+        let diagnostics = parse_log(log);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].file.as_deref(), Some("main.tex"));
+        assert_eq!(diagnostics[0].line, Some(3));
+        assert_eq!(diagnostics[0].kind, "missing_file");
+    }
+
+    #[test]
+    fn test_parse_log_classifies_warnings_and_bad_boxes() {
+        let log = "(./main.tex\n\
+LaTeX Warning: Reference `fig:arch' on page 1 undefined on input line 42.\n\
+\n\
+Package natbib Warning: Citation `smith2020' on page 1 undefined on input line 7\n\
+(natbib)                .\n\
+\n\
+Overfull \\hbox (12.5pt too wide) in paragraph at lines 20--22\n\
+[]\\OT1/cmr/m/n/10 Some text\n\
+\n\
+LaTeX Warning: Label(s) may have changed. Rerun to get cross-references right.\n\
+\n\
+)\n";
+
+        let diagnostics = parse_log(log);
+        let kinds: Vec<&str> = diagnostics.iter().map(|d| d.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "undefined_reference",
+                "undefined_citation",
+                "bad_box",
+                "rerun"
+            ]
+        );
+        assert_eq!(diagnostics[0].line, Some(42));
+        assert_eq!(diagnostics[0].file.as_deref(), Some("main.tex"));
+        assert_eq!(diagnostics[2].severity, "info");
+        assert_eq!(diagnostics[2].line, Some(20));
+    }
+
+    #[test]
+    fn test_unwrap_lines_joins_wrapped_paths() {
+        let first = format!("({}", "a".repeat(LOG_WRAP_LIMIT - 1));
+        let log = format!("{}\nbc/file.tex)\n", first);
+        let lines = unwrap_lines(&log);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with("bc/file.tex)"));
+    }
+
+    #[test]
+    fn test_classify_line_is_strict_about_errors() {
+        assert_eq!(classify_line("(./error-handling.tex"), (false, false));
+        assert_eq!(
+            classify_line("! Undefined control sequence."),
+            (true, false)
+        );
+        assert_eq!(
+            classify_line("./main.tex:5: Missing $ inserted."),
+            (true, false)
+        );
+        assert_eq!(
+            classify_line("LaTeX Warning: There were undefined references."),
+            (false, true)
+        );
+        assert_eq!(
+            classify_line("Overfull \\hbox (1.0pt too wide) in paragraph at lines 1--2"),
+            (false, true)
+        );
+    }
+}
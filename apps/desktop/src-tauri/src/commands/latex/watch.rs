@@ -0,0 +1,233 @@
+use super::{run_compile_job, BuildDirs, CompileRequest, LaTeXCompilationState};
+use crate::commands::fs::should_ignore_path;
+use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+
+/// Quiet period after the last change before a rebuild starts
+const WATCH_DEBOUNCE_MS: u64 = 300;
+
+/// Source, style and figure files whose changes trigger a rebuild
+const WATCHED_EXTENSIONS: &[&str] = &[
+    "tex", "ltx", "bib", "sty", "cls", "bst", "bbx", "cbx", "png", "jpg", "jpeg", "pdf", "eps",
+    "svg",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchStatusEvent {
+    pub job_id: String,
+    /// "watching", "building", "succeeded", "failed", "cancelled" or "stopped"
+    pub status: String,
+    /// Project-relative paths whose changes triggered the build
+    pub changed_files: Vec<String>,
+    pub message: Option<String>,
+}
+
+/// Documents being rebuilt on change, keyed by compile job id.
+///
+/// Dropping a watcher closes its change channel, which ends the rebuild loop.
+#[derive(Default)]
+pub struct CompileWatches {
+    watchers: Mutex<HashMap<String, RecommendedWatcher>>,
+}
+
+impl CompileWatches {
+    /// Start watching the request's project, replacing any watch on the same job id.
+    pub(super) fn start(
+        &self,
+        app: &AppHandle,
+        mut request: CompileRequest,
+    ) -> Result<String, String> {
+        let job_id = request.job_id();
+        request.options.job_id = Some(job_id.clone());
+
+        let project = std::fs::canonicalize(&request.directory)
+            .map_err(|e| format!("Directory not found: {} ({})", request.directory, e))?;
+        let dirs = BuildDirs::resolve(
+            &project.to_string_lossy(),
+            request.options.output_directory.as_deref(),
+            request.options.aux_directory.as_deref(),
+        );
+        let job_name = Path::new(&request.main_file)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let watch_root = project.clone();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<notify::Event, notify::Error>| {
+                let Ok(event) = res else {
+                    return;
+                };
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    if let Some(relative) = document_input(&path, &watch_root, &dirs, &job_name) {
+                        let _ = sender.send(relative);
+                    }
+                }
+            },
+            Config::default(),
+        )
+        .map_err(|e| format!("Failed to create watcher: {}", e))?;
+        watcher
+            .watch(&project, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch directory: {}", e))?;
+
+        self.watchers
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(job_id.clone(), watcher);
+
+        emit_watch_status(app, &job_id, "watching", Vec::new(), None);
+        tauri::async_runtime::spawn(rebuild_loop(app.clone(), job_id.clone(), request, receiver));
+        Ok(job_id)
+    }
+
+    /// Stop one watch, or every watch when `job_id` is None. Returns the stopped job ids.
+    pub fn stop(&self, job_id: Option<&str>) -> Result<Vec<String>, String> {
+        let mut watchers = self.watchers.lock().map_err(|e| e.to_string())?;
+        match job_id {
+            Some(id) => match watchers.remove(id) {
+                Some(_) => Ok(vec![id.to_string()]),
+                None => Err(format!("No watch with id {}", id)),
+            },
+            None => Ok(watchers.drain().map(|(id, _)| id).collect()),
+        }
+    }
+}
+
+/// Project-relative path of a changed file, if the change should trigger a rebuild.
+fn document_input(path: &Path, project: &Path, dirs: &BuildDirs, job_name: &str) -> Option<String> {
+    let relative = path.strip_prefix(project).ok()?;
+    if should_ignore_path(relative)
+        || relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+    {
+        return None;
+    }
+
+    let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+    if !WATCHED_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+
+    // Never rebuild because of our own output
+    if path == dirs.pdf(job_name) {
+        return None;
+    }
+    for build_dir in [&dirs.output, &dirs.aux] {
+        if build_dir != project && path.starts_with(build_dir) {
+            return None;
+        }
+    }
+
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// Collect changes until they settle, then start a build. Runs until the watcher is dropped.
+async fn rebuild_loop(
+    app: AppHandle,
+    job_id: String,
+    request: CompileRequest,
+    mut changes: mpsc::UnboundedReceiver<String>,
+) {
+    // Bring the PDF up to date before waiting for edits
+    start_build(&app, &job_id, &request, Vec::new());
+
+    let mut pending = BTreeSet::new();
+    loop {
+        let change = if pending.is_empty() {
+            changes.recv().await
+        } else {
+            match tokio::time::timeout(Duration::from_millis(WATCH_DEBOUNCE_MS), changes.recv())
+                .await
+            {
+                Ok(change) => change,
+                Err(_) => {
+                    let changed = std::mem::take(&mut pending).into_iter().collect();
+                    start_build(&app, &job_id, &request, changed);
+                    continue;
+                }
+            }
+        };
+        match change {
+            Some(path) => {
+                pending.insert(path);
+            }
+            None => break,
+        }
+    }
+}
+
+/// Run a build in the background. Submitting under the same job id cancels any build in flight.
+fn start_build(app: &AppHandle, job_id: &str, request: &CompileRequest, changed: Vec<String>) {
+    let app = app.clone();
+    let job_id = job_id.to_string();
+    let request = request.clone();
+    tauri::async_runtime::spawn(async move {
+        emit_watch_status(&app, &job_id, "building", changed.clone(), None);
+        let state = app.state::<LaTeXCompilationState>();
+        let (status, message) = match run_compile_job(&app, &state.jobs, request).await {
+            Ok(result) if result.success => ("succeeded", None),
+            Ok(result) => ("failed", result.error),
+            Err(e) if e == "Compilation was cancelled" => ("cancelled", None),
+            Err(e) => ("failed", Some(e)),
+        };
+        emit_watch_status(&app, &job_id, status, changed, message);
+    });
+}
+
+pub(super) fn emit_watch_status(
+    app: &AppHandle,
+    job_id: &str,
+    status: &str,
+    changed_files: Vec<String>,
+    message: Option<String>,
+) {
+    let _ = app.emit(
+        "latex-watch-status",
+        WatchStatusEvent {
+            job_id: job_id.to_string(),
+            status: status.to_string(),
+            changed_files,
+            message,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_input_filters_unrelated_and_build_files() {
+        let project = Path::new("/work/paper");
+        let dirs = BuildDirs::resolve("/work/paper", None, None);
+
+        assert_eq!(
+            document_input(&project.join("chapters/intro.tex"), project, &dirs, "main"),
+            Some("chapters/intro.tex".to_string())
+        );
+        assert_eq!(
+            document_input(&project.join("figures/plot.PDF"), project, &dirs, "main"),
+            Some("figures/plot.PDF".to_string())
+        );
+        assert!(document_input(&project.join("main.pdf"), project, &dirs, "main").is_none());
+        assert!(document_input(&project.join("main.aux"), project, &dirs, "main").is_none());
+        assert!(document_input(&project.join(".git/index.tex"), project, &dirs, "main").is_none());
+        assert!(document_input(Path::new("/elsewhere/a.tex"), project, &dirs, "main").is_none());
+
+        let dirs = BuildDirs::resolve("/work/paper", Some("out"), Some("aux"));
+        assert!(document_input(&project.join("aux/plot.pdf"), project, &dirs, "main").is_none());
+        assert!(document_input(&project.join("out/main.pdf"), project, &dirs, "main").is_none());
+    }
+}
@@ -99,6 +99,11 @@ pub fn run() {
             commands::latex::latex_detect_main_file,
             commands::latex::latex_compile,
             commands::latex::latex_stop_compilation,
+            commands::latex::latex_cancel_job,
+            commands::latex::latex_list_jobs,
+            commands::latex::latex_set_max_parallel_jobs,
+            commands::latex::latex_watch_start,
+            commands::latex::latex_watch_stop,
             commands::latex::latex_clean_aux_files,
             commands::latex::latex_synctex_edit,
             commands::latex::latex_install_synctex,
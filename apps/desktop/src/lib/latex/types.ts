@@ -20,19 +20,80 @@ export interface LaTeXSettings {
 
 export type CompilationStatus = "idle" | "compiling" | "success" | "error";
 
+export interface LaTeXDiagnostic {
+  severity: "error" | "warning" | "info";
+  kind:
+    | "general"
+    | "missing_file"
+    | "missing_font"
+    | "undefined_reference"
+    | "undefined_citation"
+    | "bad_box"
+    | "rerun";
+  file: string | null;
+  line: number | null;
+  message: string;
+  context: string[];
+}
+
+export interface CompileOptions {
+  job_id?: string | null;
+  output_directory?: string | null;
+  aux_directory?: string | null;
+}
+
 export interface CompilationResult {
+  job_id: string;
   success: boolean;
   exit_code: number | null;
   pdf_path: string | null;
+  synctex_path: string | null;
   error: string | null;
+  diagnostics: LaTeXDiagnostic[];
 }
 
 export interface CompileOutputEvent {
+  job_id: string;
   line: string;
   is_error: boolean;
   is_warning: boolean;
 }
 
+export interface CompileProgressEvent {
+  job_id: string;
+  pass: number;
+  max_passes: number;
+  tool: string;
+  message: string;
+}
+
+export interface CompileDiagnosticsEvent {
+  job_id: string;
+  diagnostics: LaTeXDiagnostic[];
+}
+
+export interface CompileJobInfo {
+  job_id: string;
+  directory: string;
+  main_file: string;
+  compiler: string;
+  status: "queued" | "running";
+  submitted_at: number;
+}
+
+export interface WatchStatusEvent {
+  job_id: string;
+  status:
+    | "watching"
+    | "building"
+    | "succeeded"
+    | "failed"
+    | "cancelled"
+    | "stopped";
+  changed_files: string[];
+  message: string | null;
+}
+
 export const DEFAULT_LATEX_SETTINGS: LaTeXSettings = {
   mainFile: null,
 };